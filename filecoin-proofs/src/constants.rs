@@ -0,0 +1,17 @@
+// Sector sizes, expressed in bytes. Every size here must be a power of two so that
+// `shape_for_sector_size`/`shape_info` in `types::sector_shapes` can derive a tree shape
+// for it.
+
+// Named to match the sibling `storage_proofs_update` constant of the same name: it's the
+// smallest test/dev sector, a single 32-byte node, not a literal 1024-byte sector.
+pub const SECTOR_SIZE_1_KIB: u64 = 1 << 5;
+pub const SECTOR_SIZE_2_KIB: u64 = 1 << 11;
+pub const SECTOR_SIZE_4_KIB: u64 = 1 << 12;
+pub const SECTOR_SIZE_16_KIB: u64 = 1 << 14;
+pub const SECTOR_SIZE_32_KIB: u64 = 1 << 15;
+pub const SECTOR_SIZE_8_MIB: u64 = 1 << 23;
+pub const SECTOR_SIZE_16_MIB: u64 = 1 << 24;
+pub const SECTOR_SIZE_512_MIB: u64 = 1 << 29;
+pub const SECTOR_SIZE_1_GIB: u64 = 1 << 30;
+pub const SECTOR_SIZE_32_GIB: u64 = 1 << 35;
+pub const SECTOR_SIZE_64_GIB: u64 = 1 << 36;
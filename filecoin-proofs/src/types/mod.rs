@@ -0,0 +1,72 @@
+use std::convert::TryFrom;
+
+use storage_proofs_core::hasher::PoseidonHasher;
+
+use crate::constants::{
+    SECTOR_SIZE_16_KIB, SECTOR_SIZE_16_MIB, SECTOR_SIZE_1_GIB, SECTOR_SIZE_1_KIB,
+    SECTOR_SIZE_2_KIB, SECTOR_SIZE_32_GIB, SECTOR_SIZE_32_KIB, SECTOR_SIZE_4_KIB,
+    SECTOR_SIZE_512_MIB, SECTOR_SIZE_64_GIB, SECTOR_SIZE_8_MIB,
+};
+
+mod sector_shapes;
+
+pub use sector_shapes::*;
+
+/// The hasher used to build the Merkle trees backing a sector, regardless of its shape.
+pub type DefaultTreeHasher = PoseidonHasher;
+
+/// The set of sector sizes this crate knows how to build a Merkle tree shape for. Each
+/// variant corresponds 1:1 with one of the `SECTOR_SIZE_*` byte-size constants.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SectorSize {
+    KiB1,
+    KiB2,
+    KiB4,
+    KiB16,
+    KiB32,
+    MiB8,
+    MiB16,
+    MiB512,
+    GiB1,
+    GiB32,
+    GiB64,
+}
+
+impl TryFrom<u64> for SectorSize {
+    type Error = sector_shapes::UnsupportedSectorSize;
+
+    fn try_from(sector_size: u64) -> Result<Self, Self::Error> {
+        match sector_size {
+            SECTOR_SIZE_1_KIB => Ok(SectorSize::KiB1),
+            SECTOR_SIZE_2_KIB => Ok(SectorSize::KiB2),
+            SECTOR_SIZE_4_KIB => Ok(SectorSize::KiB4),
+            SECTOR_SIZE_16_KIB => Ok(SectorSize::KiB16),
+            SECTOR_SIZE_32_KIB => Ok(SectorSize::KiB32),
+            SECTOR_SIZE_8_MIB => Ok(SectorSize::MiB8),
+            SECTOR_SIZE_16_MIB => Ok(SectorSize::MiB16),
+            SECTOR_SIZE_512_MIB => Ok(SectorSize::MiB512),
+            SECTOR_SIZE_1_GIB => Ok(SectorSize::GiB1),
+            SECTOR_SIZE_32_GIB => Ok(SectorSize::GiB32),
+            SECTOR_SIZE_64_GIB => Ok(SectorSize::GiB64),
+            _ => Err(sector_shapes::UnsupportedSectorSize(sector_size)),
+        }
+    }
+}
+
+impl From<SectorSize> for u64 {
+    fn from(sector_size: SectorSize) -> Self {
+        match sector_size {
+            SectorSize::KiB1 => SECTOR_SIZE_1_KIB,
+            SectorSize::KiB2 => SECTOR_SIZE_2_KIB,
+            SectorSize::KiB4 => SECTOR_SIZE_4_KIB,
+            SectorSize::KiB16 => SECTOR_SIZE_16_KIB,
+            SectorSize::KiB32 => SECTOR_SIZE_32_KIB,
+            SectorSize::MiB8 => SECTOR_SIZE_8_MIB,
+            SectorSize::MiB16 => SECTOR_SIZE_16_MIB,
+            SectorSize::MiB512 => SECTOR_SIZE_512_MIB,
+            SectorSize::GiB1 => SECTOR_SIZE_1_GIB,
+            SectorSize::GiB32 => SECTOR_SIZE_32_GIB,
+            SectorSize::GiB64 => SECTOR_SIZE_64_GIB,
+        }
+    }
+}
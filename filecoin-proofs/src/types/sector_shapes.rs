@@ -3,9 +3,9 @@ use typenum::{U0, U2, U8};
 
 use crate::{
     constants::{
-        SECTOR_SIZE_16_KIB, SECTOR_SIZE_16_MIB, SECTOR_SIZE_1_GIB, SECTOR_SIZE_2_KIB,
-        SECTOR_SIZE_32_GIB, SECTOR_SIZE_32_KIB, SECTOR_SIZE_4_KIB, SECTOR_SIZE_512_MIB,
-        SECTOR_SIZE_64_GIB, SECTOR_SIZE_8_MIB,
+        SECTOR_SIZE_16_KIB, SECTOR_SIZE_16_MIB, SECTOR_SIZE_1_GIB, SECTOR_SIZE_1_KIB,
+        SECTOR_SIZE_2_KIB, SECTOR_SIZE_32_GIB, SECTOR_SIZE_32_KIB, SECTOR_SIZE_4_KIB,
+        SECTOR_SIZE_512_MIB, SECTOR_SIZE_64_GIB, SECTOR_SIZE_8_MIB,
     },
     types::DefaultTreeHasher,
 };
@@ -17,6 +17,7 @@ pub type SectorShapeSub8 = LCTree<DefaultTreeHasher, U8, U8, U0>;
 pub type SectorShapeTop2 = LCTree<DefaultTreeHasher, U8, U8, U2>;
 
 // Specific size constants by shape
+pub type SectorShape1KiB = SectorShapeBase;
 pub type SectorShape2KiB = SectorShapeBase;
 pub type SectorShape8MiB = SectorShapeBase;
 pub type SectorShape512MiB = SectorShapeBase;
@@ -33,7 +34,7 @@ pub type SectorShape64GiB = SectorShapeTop2;
 
 pub fn is_sector_shape_base(sector_size: u64) -> bool {
     match sector_size {
-        SECTOR_SIZE_2_KIB | SECTOR_SIZE_8_MIB | SECTOR_SIZE_512_MIB => true,
+        SECTOR_SIZE_1_KIB | SECTOR_SIZE_2_KIB | SECTOR_SIZE_8_MIB | SECTOR_SIZE_512_MIB => true,
         _ => false,
     }
 }
@@ -59,6 +60,145 @@ pub fn is_sector_shape_top2(sector_size: u64) -> bool {
     }
 }
 
+/// The base/sub/top tree arities derived for a given sector size, along with the total
+/// number of (32-byte) leaf nodes the sector expands into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShapeDescriptor {
+    pub base_arity: usize,
+    pub sub_arity: usize,
+    pub top_arity: usize,
+    pub node_count: u64,
+}
+
+/// Derives the canonical `(base, sub, top)` arities for an arbitrary power-of-two sector
+/// size, generalizing the algorithm used to pick most of the frozen `SectorShape*` type
+/// aliases above so callers can compute a shape for sizes that aren't in that list without
+/// editing this crate.
+///
+/// The base tree takes the largest power-of-eight number of nodes that fits, capped at
+/// `log_max_base` (4 GiB worth of nodes); whatever remains goes into sub/top trees. So
+/// sub/top trees appear both when the node count is not a power of eight (e.g. 32 KiB) and
+/// once the base tree would exceed that cap.
+/// A few of the smaller frozen aliases were hand-picked for test/dev purposes rather than
+/// derived from this rule and disagree with it: `SectorShape16KiB` is `(8, 8, 0)` here but
+/// this function returns `(8, 0, 0)`, and `SectorShape32KiB` is `(8, 8, 2)` here but this
+/// function returns `(8, 2, 0)`. Do not use this function to validate the shape of those two
+/// sizes; it is intended for sizes outside the frozen list.
+pub fn shape_descriptor_for_sector_size(sector_size: u64) -> ShapeDescriptor {
+    checked_shape_descriptor_for_sector_size(sector_size).unwrap_or_else(|| {
+        panic!(
+            "sector size must be a power of two of at least 32 bytes, got {}",
+            sector_size
+        )
+    })
+}
+
+/// Like [`shape_descriptor_for_sector_size`], but returns `None` instead of panicking if
+/// `sector_size` is not a power of two of at least 32 bytes (one 32-byte node).
+fn checked_shape_descriptor_for_sector_size(sector_size: u64) -> Option<ShapeDescriptor> {
+    if sector_size.count_ones() != 1 {
+        return None;
+    }
+    let log_byte_size = sector_size.trailing_zeros();
+    if log_byte_size < 5 {
+        // Smaller than a single 32-byte node; there's no shape to derive.
+        return None;
+    }
+    let log_nodes = log_byte_size - 5; // 2^5 = 32-byte nodes
+
+    let max_tree_log = 3; // Largest allowable arity. The optimal shape.
+
+    let log_max_base = 27; // 4 GiB worth of nodes
+    let log_base = max_tree_log; // Base must be oct trees.
+    let log_in_base = u32::min(log_max_base, (log_nodes / log_base) * log_base); // How many nodes in base?
+
+    let log_upper = log_nodes - log_in_base; // Nodes in sub and upper combined.
+    let log_rem = log_upper % max_tree_log; // Remainder after filling optimal trees.
+
+    let (log_sub, log_top) = {
+        // Are the upper trees empty?
+        if log_upper > 0 {
+            // Do we need a remainder tree?
+            if log_rem == 0 {
+                (Some(max_tree_log), None) // No remainder tree, fill the sub tree optimally.
+            } else {
+                // Need a remainder tree.
+
+                // Do we have room for another max tree?
+                if log_upper > max_tree_log {
+                    // There is room. Use the sub tree for as much overflow as we can fit optimally.
+                    // And put the rest in the top tree.
+                    (Some(max_tree_log), Some(log_rem))
+                } else {
+                    // Can't fit another max tree.
+                    // Just put the remainder in the sub tree.
+                    (Some(log_rem), None)
+                }
+            }
+        } else {
+            // Upper trees are empty.
+            (None, None)
+        }
+    };
+
+    let base_arity = 1 << log_base;
+    let sub_arity = if let Some(l) = log_sub { 1 << l } else { 0 };
+    let top_arity = if let Some(l) = log_top { 1 << l } else { 0 };
+
+    Some(ShapeDescriptor {
+        base_arity,
+        sub_arity,
+        top_arity,
+        node_count: 1 << log_nodes,
+    })
+}
+
+/// Convenience wrapper around [`shape_descriptor_for_sector_size`] for callers that only
+/// want the bare `(base, sub, top)` tuple. Panics under the same conditions.
+pub fn shape_for_sector_size(sector_size: u64) -> (usize, usize, usize) {
+    let descriptor = shape_descriptor_for_sector_size(sector_size);
+    (descriptor.base_arity, descriptor.sub_arity, descriptor.top_arity)
+}
+
+/// Tree-shape introspection for a sector size: the base/sub/top arities, how many base
+/// trees that implies, and the total number of (32-byte) leaf nodes across the sector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShapeInfo {
+    pub base_arity: usize,
+    pub sub_arity: usize,
+    pub top_arity: usize,
+    pub base_tree_count: usize,
+    pub node_count: u64,
+}
+
+/// Reports how many sub-trees and top-trees a sector size produces, how many base trees
+/// that implies, and the total leaf node count, derived from the same canonical shape
+/// mapping as [`shape_for_sector_size`]. Returns `None` if `sector_size` is not a power of
+/// two of at least 32 bytes. A single source of truth for tooling (sector import,
+/// tree-cache sizing, proof-size estimation) instead of re-deriving arities from the
+/// `MerkleTreeTrait` type aliases.
+pub fn shape_info(sector_size: u64) -> Option<ShapeInfo> {
+    let descriptor = checked_shape_descriptor_for_sector_size(sector_size)?;
+    let sub_tree_count = if descriptor.sub_arity == 0 {
+        1
+    } else {
+        descriptor.sub_arity
+    };
+    let top_tree_count = if descriptor.top_arity == 0 {
+        1
+    } else {
+        descriptor.top_arity
+    };
+
+    Some(ShapeInfo {
+        base_arity: descriptor.base_arity,
+        sub_arity: descriptor.sub_arity,
+        top_arity: descriptor.top_arity,
+        base_tree_count: sub_tree_count * top_tree_count,
+        node_count: descriptor.node_count,
+    })
+}
+
 /// Calls a function with the type hint of the sector shape matching the provided sector.
 /// Panics if provided with an unknown sector size.
 #[macro_export]
@@ -69,6 +209,9 @@ macro_rules! with_shape_enum {
     ($size:expr, $f:ident, $($args:expr,)*) => {
         #[allow(unreachable_patterns)]
         match $size {
+            $crate::types::SectorSize::KiB1 => {
+              $f::<$crate::types::SectorShape1KiB>($($args),*)
+            },
             $crate::types::SectorSize::KiB2 => {
               $f::<$crate::types::SectorShape2KiB>($($args),*)
             },
@@ -125,61 +268,106 @@ macro_rules! with_shape {
     };
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    use generic_array::typenum::Unsigned;
-    use storage_proofs_core::merkle::MerkleTreeTrait;
+/// Error returned by [`try_with_shape!`] and [`try_with_shape_enum!`] when the sector size
+/// does not correspond to any known sector shape. Unlike the panicking `with_shape!`/
+/// `with_shape_enum!` macros, this lets callers that accept sector sizes from untrusted
+/// input (e.g. RPC) reject bad input gracefully instead of aborting the process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnsupportedSectorSize(pub u64);
 
-    fn canonical_shape(sector_size: u64) -> (usize, usize, usize) {
-        // This could perhaps be cleaned up, but I think it expresses the intended constraints
-        // and is consistent with our current hard-coded size->shape mappings.
-        assert_eq!(sector_size.count_ones(), 1);
-        let log_byte_size = sector_size.trailing_zeros();
-        let log_nodes = log_byte_size - 5; // 2^5 = 32-byte nodes
+impl std::fmt::Display for UnsupportedSectorSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unsupported sector size: {}", self.0)
+    }
+}
 
-        let max_tree_log = 3; // Largest allowable arity. The optimal shape.
+impl std::error::Error for UnsupportedSectorSize {}
 
-        let log_max_base = 27; // 4 GiB worth of nodes
-        let log_base = max_tree_log; // Base must be oct trees.x
-        let log_in_base = u32::min(log_max_base, (log_nodes / log_base) * log_base); // How many nodes in base?
+/// Calls a function with the type hint of the sector shape matching the provided
+/// `SectorSize`, like [`with_shape_enum!`], but returns `Err(UnsupportedSectorSize)` instead
+/// of panicking if `$size` is not a recognized variant. `$f` must return a `Result` whose
+/// error type implements `From<UnsupportedSectorSize>`.
+#[macro_export]
+macro_rules! try_with_shape_enum {
+    ($size:expr, $f:ident) => {
+        try_with_shape_enum!($size, $f,)
+    };
+    ($size:expr, $f:ident, $($args:expr,)*) => {
+        #[allow(unreachable_patterns)]
+        match $size {
+            $crate::types::SectorSize::KiB1 => {
+              $f::<$crate::types::SectorShape1KiB>($($args),*)
+            },
+            $crate::types::SectorSize::KiB2 => {
+              $f::<$crate::types::SectorShape2KiB>($($args),*)
+            },
+            $crate::types::SectorSize::KiB4 => {
+              $f::<$crate::types::SectorShape4KiB>($($args),*)
+            },
+            $crate::types::SectorSize::KiB16 => {
+              $f::<$crate::types::SectorShape16KiB>($($args),*)
+            },
+            $crate::types::SectorSize::KiB32 => {
+              $f::<$crate::types::SectorShape32KiB>($($args),*)
+            },
+            $crate::types::SectorSize::MiB8 => {
+              $f::<$crate::types::SectorShape8MiB>($($args),*)
+            },
+            $crate::types::SectorSize::MiB16 => {
+              $f::<$crate::types::SectorShape16MiB>($($args),*)
+            },
+            $crate::types::SectorSize::MiB512 => {
+              $f::<$crate::types::SectorShape512MiB>($($args),*)
+            },
+            $crate::types::SectorSize::GiB1=> {
+              $f::<$crate::types::SectorShape1GiB>($($args),*)
+            },
+            $crate::types::SectorSize::GiB32 => {
+              $f::<$crate::types::SectorShape32GiB>($($args),*)
+            },
+            $crate::types::SectorSize::GiB64 => {
+              $f::<$crate::types::SectorShape64GiB>($($args),*)
+            },
+            _ => Err($crate::types::UnsupportedSectorSize(u64::from($size)).into()),
+        }
+    };
+    ($size:expr, $f:ident, $($args:expr),*) => {
+        try_with_shape_enum!($size, $f, $($args,)*)
+    };
+}
 
-        let log_upper = log_nodes - log_in_base; // Nodes in sub and upper combined.
-        let log_rem = log_upper % max_tree_log; // Remainder after filling optimal trees.
+/// Calls a function with the type hint of the sector shape matching the provided sector
+/// size, like [`with_shape!`], but returns `Err(UnsupportedSectorSize)` instead of panicking
+/// if `$size` does not correspond to a known sector size. `$f` must return a `Result` whose
+/// error type implements `From<UnsupportedSectorSize>`.
+#[macro_export]
+macro_rules! try_with_shape {
+    ($size:expr, $f:ident) => {
+        try_with_shape!($size, $f,)
+    };
+    ($size:expr, $f:ident, $($args:expr,)*) => {{
+        use std::convert::TryInto;
+        let raw_size: u64 = $size;
 
-        let (log_sub, log_top) = {
-            // Are the upper trees empty?
-            if log_upper > 0 {
-                // Do we need a remainder tree?
-                if log_rem == 0 {
-                    (Some(max_tree_log), None) // No remainder tree, fill the sub tree optimall.y
-                } else {
-                    // Need a remainder tree.
-
-                    // Do we have room for another max tree?
-                    if log_upper > max_tree_log {
-                        // There is room. Use the sub tree for as much overflow as we can fit optimally.
-                        // And put the rest in the top tree.
-                        (Some(max_tree_log), Some(log_rem))
-                    } else {
-                        // Can't fit another max tree.
-                        // Just put the remainder in the sub tree.
-                        (Some(log_rem), None)
-                    }
-                }
-            } else {
-                // Upper trees are empty.
-                (None, None)
+        match raw_size.try_into() {
+            Ok(e) => {
+                let e: $crate::types::SectorSize = e;
+                $crate::try_with_shape_enum!(e, $f, $($args), *)
             }
-        };
+            Err(_) => Err($crate::types::UnsupportedSectorSize(raw_size).into()),
+        }
+    }};
+    ($size:expr, $f:ident, $($args:expr),*) => {
+        try_with_shape!($size, $f, $($args,)*)
+    };
+}
 
-        let base = 1 << log_base;
-        let sub = if let Some(l) = log_sub { 1 << l } else { 0 };
-        let top = if let Some(l) = log_top { 1 << l } else { 0 };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (base, sub, top)
-    }
+    use generic_array::typenum::Unsigned;
+    use storage_proofs_core::merkle::MerkleTreeTrait;
 
     fn arities_to_usize<Tree: MerkleTreeTrait>() -> (usize, usize, usize) {
         (
@@ -191,6 +379,7 @@ mod tests {
 
     #[test]
     fn test_with_shape_macro() {
+        test_with_shape_macro_aux(SECTOR_SIZE_1_KIB);
         test_with_shape_macro_aux(SECTOR_SIZE_2_KIB);
         test_with_shape_macro_aux(SECTOR_SIZE_4_KIB);
         test_with_shape_macro_aux(SECTOR_SIZE_8_MIB);
@@ -202,7 +391,7 @@ mod tests {
     }
 
     fn test_with_shape_macro_aux(sector_size: u64) {
-        let expected = canonical_shape(sector_size);
+        let expected = shape_for_sector_size(sector_size);
         let arities = with_shape!(sector_size, arities_to_usize);
         assert_eq!(
             arities, expected,
@@ -210,4 +399,48 @@ mod tests {
             sector_size, arities, expected
         );
     }
+
+    fn ok_arities_to_usize<Tree: MerkleTreeTrait>() -> Result<(usize, usize, usize), UnsupportedSectorSize> {
+        Ok(arities_to_usize::<Tree>())
+    }
+
+    #[test]
+    fn test_try_with_shape_macro() {
+        let arities = try_with_shape!(SECTOR_SIZE_1_KIB, ok_arities_to_usize)
+            .expect("1 KiB is a supported sector size");
+        assert_eq!(arities, shape_for_sector_size(SECTOR_SIZE_1_KIB));
+
+        let arities = try_with_shape!(SECTOR_SIZE_2_KIB, ok_arities_to_usize)
+            .expect("2 KiB is a supported sector size");
+        assert_eq!(arities, shape_for_sector_size(SECTOR_SIZE_2_KIB));
+
+        let unsupported_size = 3;
+        let err = try_with_shape!(unsupported_size, ok_arities_to_usize)
+            .expect_err("3 bytes is not a supported sector size");
+        assert_eq!(err, UnsupportedSectorSize(unsupported_size));
+    }
+
+    #[test]
+    fn test_shape_info() {
+        let info = shape_info(SECTOR_SIZE_2_KIB).expect("2 KiB is a supported sector size");
+        assert_eq!((info.base_arity, info.sub_arity, info.top_arity), (8, 0, 0));
+        assert_eq!(info.base_tree_count, 1);
+
+        // For 16 KiB and 32 KiB the canonical algorithm gives (8, 0, 0) and (8, 2, 0), so
+        // `shape_info` diverges here from the hand-picked `SectorShape16KiB` (8, 8, 0) and
+        // `SectorShape32KiB` (8, 8, 2) aliases. See the caveat on
+        // `shape_descriptor_for_sector_size`.
+        let info = shape_info(SECTOR_SIZE_16_KIB).expect("16 KiB is a supported sector size");
+        assert_eq!((info.base_arity, info.sub_arity, info.top_arity), (8, 0, 0));
+        assert_eq!(info.base_tree_count, 1);
+
+        let info = shape_info(SECTOR_SIZE_32_KIB).expect("32 KiB is a supported sector size");
+        assert_eq!((info.base_arity, info.sub_arity, info.top_arity), (8, 2, 0));
+        assert_eq!(info.base_tree_count, 2);
+
+        assert_eq!(shape_info(3), None);
+        // Powers of two smaller than a single 32-byte node have no shape to derive.
+        assert_eq!(shape_info(1), None);
+        assert_eq!(shape_info(8), None);
+    }
 }